@@ -0,0 +1,250 @@
+//! Packs a scraped `page/` directory into a single portable zip archive,
+//! and loads one back into memory for `serve` to stream from.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::mime::sniff_mime;
+
+/// Name of the manifest entry within the archive.
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// An in-memory archive, keyed by archived path and paired with the content
+/// type `serve` should send for it.
+pub(crate) type ArchiveStore = HashMap<String, (String, Vec<u8>)>;
+
+/// One entry in an archive's manifest: where an asset came from, what it
+/// should be served as, and when it was captured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ManifestEntry {
+    original_url: String,
+    content_type: String,
+    captured_at: u64,
+}
+
+/// Packs every file under `dir` (the `page/` tree) into a zip at
+/// `archive_path`, alongside a `manifest.json` mapping each archived path
+/// back to its original URL, content type, and capture time.
+///
+/// Dotfiles such as the `.cache.json` sidecar from the incremental
+/// re-scrape cache are internal bookkeeping, not page content, and are
+/// excluded so archives stay a clean, immutable snapshot.
+///
+/// Returns the number of files packed.
+pub(crate) async fn pack(
+    dir: &Path,
+    archive_path: &Path,
+    website: &str,
+    captured_at: u64,
+) -> Result<usize, Box<dyn Error + Send + Sync>> {
+    let files = collect_files(dir).await?;
+
+    let mut manifest = HashMap::new();
+    let mut entries = Vec::new();
+    for file in &files {
+        let rel = file.strip_prefix(dir)?.to_string_lossy().replace('\\', "/");
+        let bytes = tokio::fs::read(file).await?;
+        manifest.insert(
+            rel.clone(),
+            ManifestEntry {
+                original_url: original_url_for(&rel, website),
+                content_type: sniff_mime(&rel, None),
+                captured_at,
+            },
+        );
+        entries.push((rel, bytes));
+    }
+    let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+
+    let archive_path = archive_path.to_path_buf();
+    let file_count = entries.len();
+    tokio::task::spawn_blocking(move || write_zip(&archive_path, entries, manifest_bytes)).await??;
+
+    Ok(file_count)
+}
+
+/// Reconstructs the URL an archived file was originally fetched from.
+///
+/// Same-origin assets live at `rel` under `website`. Cross-origin ones
+/// mirrored by [`crate::url_util::external_local_path`] live under
+/// `_external/<host>/<path>` instead, so for those the real origin host is
+/// recovered from the path rather than blanket-prefixing with `website`
+/// (which would otherwise fabricate a nonsense `website/_external/...`
+/// URL).
+///
+/// The query string is not recovered: same-origin paths were saved with
+/// it stripped, and mirrored paths carry only a hash of it. Mirrored hosts
+/// are always given an `https` scheme.
+fn original_url_for(rel: &str, website: &str) -> String {
+    if let Some(rest) = rel.strip_prefix("_external/")
+        && let Some((host, path)) = rest.split_once('/')
+    {
+        return format!("https://{host}/{path}");
+    }
+
+    format!("{website}/{rel}")
+}
+
+fn write_zip(
+    archive_path: &Path,
+    entries: Vec<(String, Vec<u8>)>,
+    manifest_bytes: Vec<u8>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    use std::io::Write;
+
+    let file = std::fs::File::create(archive_path)?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default();
+
+    for (rel, bytes) in entries {
+        zip.start_file(rel, options)?;
+        zip.write_all(&bytes)?;
+    }
+    zip.start_file(MANIFEST_FILE, options)?;
+    zip.write_all(&manifest_bytes)?;
+    zip.finish()?;
+
+    Ok(())
+}
+
+/// Loads every entry of the archive at `path` into memory, keyed by its
+/// archived path, paired with the content type `serve` should send for it.
+pub(crate) async fn load(path: &str) -> Result<ArchiveStore, Box<dyn Error + Send + Sync>> {
+    let path = path.to_string();
+    tokio::task::spawn_blocking(move || read_zip(&path)).await?
+}
+
+fn read_zip(path: &str) -> Result<ArchiveStore, Box<dyn Error + Send + Sync>> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(path)?;
+    let mut zip = zip::ZipArchive::new(file)?;
+
+    let mut manifest: HashMap<String, ManifestEntry> = HashMap::new();
+    if let Ok(mut manifest_file) = zip.by_name(MANIFEST_FILE) {
+        let mut buf = String::new();
+        manifest_file.read_to_string(&mut buf)?;
+        manifest = serde_json::from_str(&buf)?;
+    }
+
+    let mut store = HashMap::new();
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let name = entry.name().to_string();
+        if name == MANIFEST_FILE {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        let content_type = manifest
+            .get(&name)
+            .map(|m| m.content_type.clone())
+            .unwrap_or_else(|| sniff_mime(&name, None));
+        store.insert(name, (content_type, bytes));
+    }
+
+    Ok(store)
+}
+
+async fn collect_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync>> {
+    let mut stack = vec![dir.to_path_buf()];
+    let mut files = Vec::new();
+
+    while let Some(current) = stack.pop() {
+        let mut entries = tokio::fs::read_dir(&current).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let is_dotfile = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with('.'));
+            if is_dotfile {
+                continue;
+            }
+            if entry.file_type().await?.is_dir() {
+                stack.push(entry.path());
+            } else {
+                files.push(entry.path());
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pack_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("ivoclar_archive_test_{}", std::process::id()));
+        tokio::fs::create_dir_all(dir.join("js")).await.unwrap();
+        tokio::fs::write(dir.join("index.html"), b"<html></html>").await.unwrap();
+        tokio::fs::write(dir.join("js/app.js"), b"console.log(1)").await.unwrap();
+
+        let archive_path = dir.with_extension("zip");
+        let count = pack(&dir, &archive_path, "https://example.com", 1_700_000_000)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let store = load(archive_path.to_str().unwrap()).await.unwrap();
+        assert_eq!(store.len(), 2);
+
+        let (content_type, bytes) = store.get("index.html").unwrap();
+        assert_eq!(content_type, "text/html");
+        assert_eq!(bytes, b"<html></html>");
+
+        let (content_type, bytes) = store.get("js/app.js").unwrap();
+        assert_eq!(content_type, "application/javascript");
+        assert_eq!(bytes, b"console.log(1)");
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+        tokio::fs::remove_file(&archive_path).await.ok();
+    }
+
+    #[test]
+    fn test_original_url_for_same_origin() {
+        assert_eq!(
+            original_url_for("css/site.css", "https://www.ivoclar.com"),
+            "https://www.ivoclar.com/css/site.css"
+        );
+    }
+
+    #[test]
+    fn test_original_url_for_external_mirror() {
+        assert_eq!(
+            original_url_for(
+                "_external/fonts.gstatic.com/s/roboto/v30/font.woff2",
+                "https://www.ivoclar.com"
+            ),
+            "https://fonts.gstatic.com/s/roboto/v30/font.woff2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pack_excludes_cache_sidecar() {
+        let dir = std::env::temp_dir().join(format!("ivoclar_archive_test_dotfile_{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(dir.join("index.html"), b"<html></html>").await.unwrap();
+        tokio::fs::write(dir.join(".cache.json"), b"{}").await.unwrap();
+
+        let archive_path = dir.with_extension("zip");
+        let count = pack(&dir, &archive_path, "https://example.com", 1_700_000_000)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let store = load(archive_path.to_str().unwrap()).await.unwrap();
+        assert!(store.contains_key("index.html"));
+        assert!(!store.contains_key(".cache.json"));
+
+        tokio::fs::remove_dir_all(&dir).await.ok();
+        tokio::fs::remove_file(&archive_path).await.ok();
+    }
+}
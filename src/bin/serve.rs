@@ -3,8 +3,9 @@
 //! # Usage
 //!
 //! ```bash
-//! cargo run --bin serve          # Port 8080
-//! cargo run --bin serve -- 3000  # Custom port
+//! cargo run --bin serve                    # Port 8080, ./page
+//! cargo run --bin serve -- 3000            # Custom port, ./page
+//! cargo run --bin serve -- 3000 site.zip   # Custom port, archive
 //! ```
 
 use std::{env, error::Error};
@@ -17,6 +18,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .nth(1)
         .and_then(|p| p.parse().ok())
         .unwrap_or(8080);
+    let source = env::args().nth(2).unwrap_or_else(|| "page".to_string());
 
-    serve(port).await
+    serve(port, &source).await
 }
@@ -0,0 +1,197 @@
+//! On-disk `ETag`/`Last-Modified` cache so re-running a scrape only
+//! redownloads assets that actually changed.
+//!
+//! A sidecar `page/.cache.json` file maps each local asset path to the
+//! validators from its last successful fetch, so `download_asset` can skip
+//! the network entirely while an entry is still fresh, or issue a
+//! conditional `If-None-Match`/`If-Modified-Since` request and treat a
+//! `304 Not Modified` as "keep the existing file" otherwise.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+const CACHE_FILE: &str = "page/.cache.json";
+
+/// Cached validators and freshness info for one locally-saved asset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct CacheEntry {
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+    /// Unix timestamp after which this entry must be revalidated, derived
+    /// from `Cache-Control: max-age`. `None` means always revalidate with a
+    /// conditional request — including when a server sends `Expires` but no
+    /// `max-age`, since we don't parse HTTP-date values.
+    pub(crate) fresh_until: Option<u64>,
+    /// Set when `Cache-Control: no-store` (or `no-cache`) was present, in
+    /// which case the entry is never treated as fresh even before expiry.
+    pub(crate) no_store: bool,
+}
+
+impl CacheEntry {
+    pub(crate) fn is_fresh(&self) -> bool {
+        !self.no_store && self.fresh_until.is_some_and(|expiry| now() < expiry)
+    }
+}
+
+/// Sidecar metadata store mapping each local asset path to its cache
+/// validators, persisted as `page/.cache.json`.
+#[derive(Debug, Default)]
+pub(crate) struct CacheStore {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl CacheStore {
+    /// Loads the cache from disk, starting empty if it doesn't exist yet or
+    /// can't be parsed.
+    pub(crate) async fn load() -> Self {
+        let entries = match fs::read(CACHE_FILE).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(_) => HashMap::new(),
+        };
+        Self { entries }
+    }
+
+    pub(crate) fn get(&self, local_path: &str) -> Option<&CacheEntry> {
+        self.entries.get(local_path)
+    }
+
+    pub(crate) fn insert(&mut self, local_path: String, entry: CacheEntry) {
+        self.entries.insert(local_path, entry);
+    }
+
+    /// Evicts a stale entry, e.g. one whose validator the server still
+    /// honors with a `304` but whose on-disk file is gone.
+    pub(crate) fn remove(&mut self, local_path: &str) {
+        self.entries.remove(local_path);
+    }
+
+    /// Clones out the entries so they can be persisted with [`save_entries`]
+    /// after the `std::sync::MutexGuard` protecting this store is dropped,
+    /// instead of holding it across the `.await` a direct `save` would need.
+    pub(crate) fn snapshot(&self) -> HashMap<String, CacheEntry> {
+        self.entries.clone()
+    }
+}
+
+/// Writes a set of cache entries to the sidecar file, taking a plain
+/// `HashMap` (via [`CacheStore::snapshot`]) rather than `&CacheStore` so
+/// callers behind a `std::sync::Mutex` can drop the guard before the
+/// `.await`.
+pub(crate) async fn save_entries(entries: &HashMap<String, CacheEntry>) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = Path::new(CACHE_FILE).parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let bytes = serde_json::to_vec_pretty(entries)?;
+    fs::write(CACHE_FILE, bytes).await?;
+    Ok(())
+}
+
+/// Builds a cache entry from a response's validator and freshness headers.
+pub(crate) fn entry_from_headers(headers: &HeaderMap) -> CacheEntry {
+    let etag = headers
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let last_modified = headers
+        .get(reqwest::header::LAST_MODIFIED)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let (no_store, fresh_until) = parse_cache_control(
+        headers
+            .get(reqwest::header::CACHE_CONTROL)
+            .and_then(|v| v.to_str().ok()),
+    );
+
+    CacheEntry {
+        etag,
+        last_modified,
+        fresh_until,
+        no_store,
+    }
+}
+
+/// Parses a `Cache-Control` header into `(no_store, fresh_until)`, where
+/// `fresh_until` is a unix timestamp derived from `max-age`.
+fn parse_cache_control(value: Option<&str>) -> (bool, Option<u64>) {
+    let Some(value) = value else {
+        return (false, None);
+    };
+
+    let mut no_store = false;
+    let mut max_age = None;
+    for directive in value.split(',') {
+        let directive = directive.trim();
+        if directive.eq_ignore_ascii_case("no-store") || directive.eq_ignore_ascii_case("no-cache") {
+            no_store = true;
+        } else if let Some(age) = directive
+            .split('=')
+            .nth(1)
+            .filter(|_| directive.to_ascii_lowercase().starts_with("max-age"))
+        {
+            max_age = age.trim().parse::<u64>().ok();
+        }
+    }
+
+    (no_store, max_age.map(|age| now() + age))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cache_control_no_store() {
+        let (no_store, fresh_until) = parse_cache_control(Some("no-store"));
+        assert!(no_store);
+        assert_eq!(fresh_until, None);
+    }
+
+    #[test]
+    fn test_parse_cache_control_max_age() {
+        let (no_store, fresh_until) = parse_cache_control(Some("public, max-age=3600"));
+        assert!(!no_store);
+        assert!(fresh_until.unwrap() > now());
+    }
+
+    #[test]
+    fn test_parse_cache_control_missing() {
+        assert_eq!(parse_cache_control(None), (false, None));
+    }
+
+    #[test]
+    fn test_entry_freshness() {
+        let fresh = CacheEntry {
+            fresh_until: Some(now() + 60),
+            ..Default::default()
+        };
+        assert!(fresh.is_fresh());
+
+        let expired = CacheEntry {
+            fresh_until: Some(now().saturating_sub(60)),
+            ..Default::default()
+        };
+        assert!(!expired.is_fresh());
+
+        let no_store = CacheEntry {
+            fresh_until: Some(now() + 60),
+            no_store: true,
+            ..Default::default()
+        };
+        assert!(!no_store.is_fresh());
+    }
+}
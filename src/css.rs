@@ -0,0 +1,86 @@
+//! Helpers for parsing `url(...)` and `@import` references out of CSS text.
+
+/// Extracts every `url(...)` and `@import` target from a CSS source string.
+///
+/// Skips `data:` URIs and strips surrounding quotes/whitespace. Callers are
+/// expected to resolve the returned strings against the stylesheet's own
+/// URL (not the page URL), per the CSS spec for relative references.
+pub(crate) fn extract_css_urls(css: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+
+    let mut i = 0;
+    while let Some(offset) = css[i..].find("url(") {
+        let start = i + offset + "url(".len();
+        let Some(end_rel) = css[start..].find(')') else {
+            break;
+        };
+        push_if_real(&mut urls, &css[start..start + end_rel]);
+        i = start + end_rel + 1;
+    }
+
+    for segment in css.split('@').skip(1) {
+        let Some(rest) = segment.strip_prefix("import") else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        if rest.starts_with("url(") {
+            continue; // already captured by the scan above
+        }
+        if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'')
+            && let Some(end) = rest[1..].find(quote)
+        {
+            push_if_real(&mut urls, &rest[1..1 + end]);
+        }
+    }
+
+    urls
+}
+
+fn push_if_real(urls: &mut Vec<String>, raw: &str) {
+    let raw = raw.trim().trim_matches(|c| c == '\'' || c == '"');
+    if !raw.is_empty() && !raw.starts_with("data:") {
+        urls.push(raw.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_url_unquoted() {
+        assert_eq!(
+            extract_css_urls("body { background: url(bg.png) }"),
+            vec!["bg.png".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_extract_url_quoted() {
+        assert_eq!(
+            extract_css_urls("@font-face { src: url('font.woff2') }"),
+            vec!["font.woff2".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_extract_import_statement() {
+        assert_eq!(
+            extract_css_urls("@import \"base.css\"; body { color: red }"),
+            vec!["base.css".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_extract_import_url_form_not_duplicated() {
+        assert_eq!(
+            extract_css_urls("@import url(base.css);"),
+            vec!["base.css".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_extract_skips_data_uri() {
+        assert!(extract_css_urls("background: url(data:image/png;base64,ABC)").is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+//! Recursively discovers assets referenced *inside* downloaded CSS —
+//! `@import`ed stylesheets, `@font-face` sources, background images — which
+//! never show up in the page's own `<link>`/`<img>` markup and so are
+//! invisible to [`crate::collect_resource_urls`].
+
+use std::collections::HashSet;
+
+use crate::{css::extract_css_urls, local_path_for, mime::sniff_mime, url_util::resolve_against, HostPolicy};
+
+/// Cheaply decides whether `url` is plausibly a stylesheet, without making
+/// a network request, so callers can skip handing every non-CSS asset
+/// (images, scripts, fonts, video, ...) to [`discover_css_assets`] just to
+/// have it fetch the response and sniff `Content-Type` before bailing.
+///
+/// Matches the ordinary `.css` extension plus the handful of extensionless
+/// stylesheet endpoints seen in practice, like Google Fonts'
+/// `https://fonts.googleapis.com/css?family=Roboto` or `.../css2?family=...`.
+/// `discover_css_assets` still re-checks the real `Content-Type` before
+/// parsing, so a false positive here only costs one wasted fetch rather
+/// than a wrongly-included asset.
+pub(crate) fn looks_like_stylesheet(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    if path.to_ascii_lowercase().ends_with(".css") {
+        return true;
+    }
+    matches!(path.rsplit('/').next(), Some("css") | Some("css2"))
+}
+
+/// Fetches `css_url`, extracts every `url(...)`/`@import` target — resolved
+/// against the stylesheet's own URL, not the page URL — and recurses into
+/// any of those that are themselves stylesheets, skipping anything already
+/// in `seen` to avoid cycles.
+///
+/// Every discovered reference is checked against `website`/`policy` (the
+/// same same-origin-or-allowed-host gate `download_asset` uses) *before* it
+/// is fetched or recursed into, so a denied/non-allowed host is never
+/// contacted — not even to sniff whether it's itself a stylesheet that
+/// `@import`s further assets.
+///
+/// Bails out immediately (returning nothing) unless the response's sniffed
+/// `Content-Type` is `text/css` — not whether `css_url` happens to end in
+/// `.css` — since extensionless stylesheet endpoints like Google Fonts'
+/// `//fonts.googleapis.com/css?family=Roboto` are a motivating case here.
+///
+/// Returns the full list of newly-discovered asset URLs (CSS and non-CSS)
+/// in discovery order, ready to be handed to `download_asset`.
+pub(crate) async fn discover_css_assets(
+    client: &reqwest::Client,
+    css_url: &str,
+    seen: &mut HashSet<String>,
+    website: &str,
+    policy: &HostPolicy,
+) -> Vec<String> {
+    let mut discovered = Vec::new();
+
+    let Ok(response) = client.get(css_url).send().await else {
+        return discovered;
+    };
+    let mime = sniff_mime(
+        css_url,
+        response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok()),
+    );
+    if mime != "text/css" {
+        return discovered;
+    }
+    let Ok(css) = response.text().await else {
+        return discovered;
+    };
+
+    for full_url in resolve_css_refs(&css, css_url) {
+        if !seen.insert(full_url.clone()) {
+            continue;
+        }
+        if local_path_for(&full_url, website, policy).is_none() {
+            continue;
+        }
+        discovered.push(full_url.clone());
+        if !looks_like_stylesheet(&full_url) {
+            continue;
+        }
+        discovered.extend(Box::pin(discover_css_assets(client, &full_url, seen, website, policy)).await);
+    }
+
+    discovered
+}
+
+/// Resolves every `url(...)`/`@import` target found in `css` against the
+/// stylesheet's own URL `css_url`, rather than the page URL.
+fn resolve_css_refs(css: &str, css_url: &str) -> Vec<String> {
+    extract_css_urls(css)
+        .into_iter()
+        .map(|reference| resolve_against(&reference, css_url))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_looks_like_stylesheet_extension() {
+        assert!(looks_like_stylesheet("https://example.com/assets/style.css"));
+        assert!(looks_like_stylesheet("https://example.com/assets/STYLE.CSS?v=2"));
+    }
+
+    #[test]
+    fn test_looks_like_stylesheet_extensionless_endpoint() {
+        assert!(looks_like_stylesheet("https://fonts.googleapis.com/css?family=Roboto"));
+        assert!(looks_like_stylesheet("https://fonts.googleapis.com/css2?family=Roboto"));
+    }
+
+    #[test]
+    fn test_looks_like_stylesheet_rejects_other_assets() {
+        assert!(!looks_like_stylesheet("https://example.com/img/logo.png"));
+        assert!(!looks_like_stylesheet("https://example.com/app.js"));
+    }
+
+    #[test]
+    fn test_resolve_css_refs_relative_to_stylesheet_url() {
+        let css = "@font-face { src: url(fonts/a.woff2); } @import 'base.css';";
+        let refs = resolve_css_refs(css, "https://example.com/assets/style.css");
+        assert_eq!(
+            refs,
+            vec![
+                "https://example.com/assets/fonts/a.woff2".to_string(),
+                "https://example.com/assets/base.css".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Self-contained ("monolithic") HTML output.
+//!
+//! Every external asset a page references is inlined as a `data:` URI so
+//! the result opens as a single portable file, with no `serve` binary or
+//! sibling asset directory required.
+
+use std::{collections::HashMap, error::Error};
+
+use base64::Engine;
+use thirtyfour::WebDriver;
+
+use crate::{css::extract_css_urls, mime::sniff_mime, url_util::resolve_against};
+
+/// Builds a `data:<mime>;base64,<payload>` URI from raw bytes.
+fn to_data_uri(bytes: &[u8], mime: &str) -> String {
+    format!(
+        "data:{mime};base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+/// Recursively inlines every `url(...)`/`@import` target in a stylesheet as
+/// a `data:` URI, fetching each one relative to the stylesheet's own URL.
+async fn embed_css(client: &reqwest::Client, css: &str, css_url: &str) -> String {
+    let mut out = css.to_string();
+
+    // Longest references first, so a reference that is a suffix of another
+    // (e.g. "icon.png" vs. "big-icon.png") can't clobber the longer one's
+    // replacement — see `replace_referenced` in html_rewrite.rs.
+    let mut references = extract_css_urls(css);
+    references.sort_by_key(|r| std::cmp::Reverse(r.len()));
+
+    for reference in references {
+        let full_url = resolve_against(&reference, css_url);
+
+        let Ok(response) = client.get(&full_url).send().await else {
+            continue;
+        };
+        let mime = sniff_mime(
+            &full_url,
+            response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok()),
+        );
+        let Ok(bytes) = response.bytes().await else {
+            continue;
+        };
+
+        let replacement = if mime == "text/css" {
+            let nested = String::from_utf8_lossy(&bytes).into_owned();
+            let embedded = Box::pin(embed_css(client, &nested, &full_url)).await;
+            to_data_uri(embedded.as_bytes(), "text/css")
+        } else {
+            to_data_uri(&bytes, &mime)
+        };
+
+        out = replace_css_reference(&out, &reference, &replacement);
+    }
+
+    out
+}
+
+/// Replaces `reference` with `replacement` only where it appears as a CSS
+/// reference — inside `url(...)` (quoted or bare) or a quoted `@import`
+/// target — rather than as a raw substring, so a reference that happens to
+/// be a suffix of another CSS string (e.g. "icon.png" vs. "big-icon.png")
+/// can't corrupt the other's occurrence. Mirrors `replace_referenced` in
+/// html_rewrite.rs.
+fn replace_css_reference(css: &str, reference: &str, replacement: &str) -> String {
+    let mut out = css.to_string();
+
+    for quote in ['"', '\'', ' '] {
+        let (from, to) = if quote == ' ' {
+            (format!("url({reference})"), format!("url({replacement})"))
+        } else {
+            (
+                format!("{quote}{reference}{quote}"),
+                format!("{quote}{replacement}{quote}"),
+            )
+        };
+        out = out.replace(&from, &to);
+    }
+
+    out
+}
+
+/// Inlines every asset a page references (images, scripts, stylesheets,
+/// preload/icon links, inline `style` backgrounds, `<source srcset>`) as
+/// `data:` URIs and returns the rewritten document, ready to be saved as
+/// one self-contained `.html` file.
+///
+/// Stylesheets reached via `<link rel="stylesheet">` are fetched, have
+/// their own `url(...)`/`@import` targets recursively embedded, and are
+/// inlined as a `<style>` tag rather than a data URI.
+pub(crate) async fn embed_page(
+    driver: &WebDriver,
+    client: &reqwest::Client,
+    website: &str,
+    urls: &[String],
+) -> Result<String, Box<dyn Error>> {
+    let mut replacements: HashMap<String, String> = HashMap::new();
+    // Raw (non-data-URI) embedded CSS, keyed by the original `<link>` href,
+    // used only to turn `<link rel="stylesheet">` into an inline `<style>`
+    // tag. Every other consumer (preload/icon links, `<img>`, `srcset`,
+    // inline `style` backgrounds, ...) must get a `data:` URI from
+    // `replacements` instead, or it ends up with raw CSS text stuffed into
+    // an attribute that expects a URL.
+    let mut stylesheet_css: HashMap<String, String> = HashMap::new();
+
+    for url in urls {
+        if url.starts_with("data:") || url.starts_with("blob:") {
+            continue;
+        }
+        let full_url = resolve_against(url, website);
+
+        let Ok(response) = client.get(&full_url).send().await else {
+            continue;
+        };
+        let mime = sniff_mime(
+            &full_url,
+            response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok()),
+        );
+        let Ok(bytes) = response.bytes().await else {
+            continue;
+        };
+
+        let inlined = if mime == "text/css" {
+            let css = String::from_utf8_lossy(&bytes).into_owned();
+            let embedded = embed_css(client, &css, &full_url).await;
+            stylesheet_css.insert(url.clone(), embedded.clone());
+            to_data_uri(embedded.as_bytes(), "text/css")
+        } else {
+            to_data_uri(&bytes, &mime)
+        };
+
+        replacements.insert(url.clone(), inlined);
+    }
+
+    let script = r#"
+        const [map, cssMap] = arguments;
+        const attrPairs = [
+            ['img', 'src'], ['img', 'data-src'], ['script', 'src'],
+            ['video', 'src'], ['video', 'poster'], ['audio', 'src'], ['source', 'src'],
+        ];
+        for (const [selector, attr] of attrPairs) {
+            document.querySelectorAll(selector).forEach(el => {
+                const value = el.getAttribute(attr);
+                if (value && map[value] !== undefined) el.setAttribute(attr, map[value]);
+            });
+        }
+        document.querySelectorAll('link[rel="stylesheet"]').forEach(el => {
+            const href = el.getAttribute('href');
+            if (href && cssMap[href] !== undefined) {
+                const style = document.createElement('style');
+                style.textContent = cssMap[href];
+                el.replaceWith(style);
+            }
+        });
+        document.querySelectorAll('link[rel="preload"], link[rel="icon"]').forEach(el => {
+            const href = el.getAttribute('href');
+            if (href && map[href] !== undefined) el.setAttribute('href', map[href]);
+        });
+        document.querySelectorAll('source[srcset]').forEach(el => {
+            const rewritten = el.getAttribute('srcset').split(',').map(part => {
+                const bits = part.trim().split(/\s+/);
+                const replacement = map[bits[0]] || bits[0];
+                bits[0] = replacement;
+                return bits.join(' ');
+            }).join(', ');
+            el.setAttribute('srcset', rewritten);
+        });
+        document.querySelectorAll('[style]').forEach(el => {
+            let css = el.getAttribute('style');
+            for (const [from, to] of Object.entries(map)) {
+                for (const quote of ['', '"', "'"]) {
+                    css = css.split(`url(${quote}${from}${quote})`).join(`url(${to})`);
+                }
+            }
+            el.setAttribute('style', css);
+        });
+        return document.documentElement.outerHTML;
+    "#;
+
+    let result = driver
+        .execute(
+            script,
+            vec![
+                serde_json::to_value(&replacements)?,
+                serde_json::to_value(&stylesheet_css)?,
+            ],
+        )
+        .await?;
+
+    Ok(serde_json::from_value(result.json().clone())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_data_uri_encodes_base64() {
+        let uri = to_data_uri(b"hello", "text/plain");
+        assert_eq!(uri, "data:text/plain;base64,aGVsbG8=");
+    }
+
+    #[test]
+    fn test_to_data_uri_empty_bytes() {
+        assert_eq!(to_data_uri(b"", "image/png"), "data:image/png;base64,");
+    }
+
+    #[test]
+    fn test_replace_css_reference_does_not_corrupt_suffix_collision() {
+        let css = "a { background: url(icon.png) } b { background: url(big-icon.png) }";
+        let out = replace_css_reference(css, "icon.png", "data:image/png;base64,AA==");
+        assert_eq!(
+            out,
+            "a { background: url(data:image/png;base64,AA==) } \
+             b { background: url(big-icon.png) }"
+        );
+    }
+
+    #[test]
+    fn test_replace_css_reference_matches_quoted_import() {
+        let css = "@import \"base.css\";";
+        let out = replace_css_reference(css, "base.css", "data:text/css;base64,AA==");
+        assert_eq!(out, "@import \"data:text/css;base64,AA==\";");
+    }
+}
@@ -0,0 +1,252 @@
+//! Rewrites saved HTML so asset references point at the local copies
+//! `download_asset` wrote to disk, and honors/injects a `<base>` tag.
+
+use std::collections::HashMap;
+
+/// Rewrites every asset reference in `html` that has a local copy to point
+/// at the file `download_asset` wrote under `page/`, and injects a
+/// `<base>` tag when requested and none already exists.
+///
+/// `page_url` is only used as the injected `<base href>` target; an
+/// existing `<base>` tag in the document is left untouched, since only the
+/// first one encountered is authoritative, exactly as browsers treat it.
+pub(crate) fn rewrite_html(
+    html: &str,
+    page_url: &str,
+    asset_map: &HashMap<String, String>,
+    inject_base_tag: bool,
+) -> String {
+    let mut out = html.to_string();
+
+    // Replace longest references first so a URL that is a prefix of another
+    // (e.g. "/a" vs "/a/b.png") can't clobber the longer one's replacement.
+    let mut urls: Vec<&String> = asset_map.keys().collect();
+    urls.sort_by_key(|u| std::cmp::Reverse(u.len()));
+    for url in urls {
+        let local = &asset_map[url];
+        out = replace_referenced(&out, url, &format!("/{local}"));
+    }
+
+    if inject_base_tag && extract_base_href(&out).is_none() {
+        out = insert_base_tag(&out, page_url);
+    }
+
+    out
+}
+
+/// Replaces `url` with `replacement` only where it appears as a reference —
+/// inside a quoted attribute (`src="..."`, `href='...'`) or a CSS `url(...)`
+/// — rather than as a raw substring anywhere in the document. A raw
+/// substring match would also corrupt unrelated text that merely contains
+/// `url` as a fragment, e.g. an asset `"app.js"` colliding with `"myapp.js"`
+/// inside an inline `<script>`.
+fn replace_referenced(html: &str, url: &str, replacement: &str) -> String {
+    let mut out = html.to_string();
+
+    for quote in ['"', '\''] {
+        let from = format!("{quote}{url}{quote}");
+        let to = format!("{quote}{replacement}{quote}");
+        out = out.replace(&from, &to);
+    }
+
+    let bare_from = format!("url({url})");
+    let bare_to = format!("url({replacement})");
+    out = out.replace(&bare_from, &bare_to);
+
+    replace_in_srcset(&out, url, replacement)
+}
+
+/// Rewrites `url` to `replacement` inside every `srcset="..."` attribute's
+/// comma-separated candidate list.
+///
+/// `srcset` packs several URLs into one attribute value, each followed by
+/// its own width/density descriptor (`"img-400.jpg 400w, img-800.jpg
+/// 800w"`), so a candidate is never the attribute's *entire* quoted value
+/// and the whole-string match above can't see it. Each candidate is
+/// compared and replaced on its own, with the descriptor carried over
+/// unchanged.
+fn replace_in_srcset(html: &str, url: &str, replacement: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(rel_idx) = rest.find("srcset=") {
+        let attr_start = rel_idx + "srcset=".len();
+        out.push_str(&rest[..attr_start]);
+
+        let Some(quote) = rest[attr_start..].chars().next().filter(|&c| c == '"' || c == '\'') else {
+            rest = &rest[attr_start..];
+            continue;
+        };
+        let value_start = attr_start + quote.len_utf8();
+        let Some(value_end_rel) = rest[value_start..].find(quote) else {
+            rest = &rest[attr_start..];
+            continue;
+        };
+        let value_end = value_start + value_end_rel;
+        let value = &rest[value_start..value_end];
+
+        let rewritten: Vec<String> = value
+            .split(',')
+            .map(|candidate| {
+                let trimmed = candidate.trim();
+                let (candidate_url, descriptor) = trimmed.split_once(char::is_whitespace).unwrap_or((trimmed, ""));
+                match (candidate_url == url, descriptor.is_empty()) {
+                    (true, true) => replacement.to_string(),
+                    (true, false) => format!("{replacement} {descriptor}"),
+                    (false, _) => trimmed.to_string(),
+                }
+            })
+            .collect();
+
+        out.push(quote);
+        out.push_str(&rewritten.join(", "));
+        out.push(quote);
+
+        rest = &rest[value_end + quote.len_utf8()..];
+    }
+    out.push_str(rest);
+
+    out
+}
+
+/// Returns the `href` of the document's first `<base>` tag, if any.
+pub(crate) fn extract_base_href(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let tag_start = lower.find("<base")?;
+    let tag_end = lower[tag_start..].find('>')? + tag_start;
+    let tag = &html[tag_start..tag_end];
+
+    let lower_tag = tag.to_ascii_lowercase();
+    let href_start = lower_tag.find("href=")? + "href=".len();
+    let quote = tag.as_bytes().get(href_start).copied()? as char;
+    if quote != '"' && quote != '\'' {
+        return None;
+    }
+    let value_start = href_start + 1;
+    let value_end = tag[value_start..].find(quote)? + value_start;
+    Some(tag[value_start..value_end].to_string())
+}
+
+fn insert_base_tag(html: &str, page_url: &str) -> String {
+    let tag = format!("<base href=\"{page_url}/\">");
+    if let Some(insert_at) = find_head_tag_end(html) {
+        let mut out = String::with_capacity(html.len() + tag.len());
+        out.push_str(&html[..insert_at]);
+        out.push_str(&tag);
+        out.push_str(&html[insert_at..]);
+        out
+    } else {
+        format!("{tag}{html}")
+    }
+}
+
+/// Finds the byte offset just past the end of the opening `<head ...>` tag,
+/// matching `<head` followed by `>` or whitespace/attributes — not just the
+/// literal `<head>` — since real pages commonly carry attributes on `<head>`
+/// (e.g. `<head lang="en">`, `<head class="...">`).
+fn find_head_tag_end(html: &str) -> Option<usize> {
+    let lower = html.to_ascii_lowercase();
+    let mut search_from = 0;
+    loop {
+        let rel = lower[search_from..].find("<head")?;
+        let tag_start = search_from + rel;
+        let after = tag_start + "<head".len();
+        match lower.as_bytes().get(after) {
+            Some(b'>') | Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => {
+                return Some(lower[after..].find('>')? + after + 1);
+            }
+            _ => {
+                // e.g. `<header>` — not a `<head>` tag, keep looking.
+                search_from = after;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_replaces_known_asset() {
+        let mut map = HashMap::new();
+        map.insert("https://example.com/js/app.js".to_string(), "js/app.js".to_string());
+        let html = r#"<script src="https://example.com/js/app.js"></script>"#;
+        let out = rewrite_html(html, "https://example.com", &map, false);
+        assert_eq!(out, r#"<script src="/js/app.js"></script>"#);
+    }
+
+    #[test]
+    fn test_rewrite_does_not_corrupt_unrelated_substring_matches() {
+        let mut map = HashMap::new();
+        map.insert("app.js".to_string(), "js/app.js".to_string());
+        let html = r#"<script src="app.js"></script><script>var x = "myapp.js";</script>"#;
+        let out = rewrite_html(html, "https://example.com", &map, false);
+        assert!(out.contains(r#"src="/js/app.js""#));
+        assert!(out.contains(r#""myapp.js""#));
+    }
+
+    #[test]
+    fn test_rewrite_matches_css_url_context() {
+        let mut map = HashMap::new();
+        map.insert("bg.png".to_string(), "images/bg.png".to_string());
+        let html = r#"<div style="background: url(bg.png)"></div>"#;
+        let out = rewrite_html(html, "https://example.com", &map, false);
+        assert_eq!(
+            out,
+            r#"<div style="background: url(/images/bg.png)"></div>"#
+        );
+    }
+
+    #[test]
+    fn test_rewrite_replaces_srcset_candidates() {
+        let mut map = HashMap::new();
+        map.insert("img-400.jpg".to_string(), "images/img-400.jpg".to_string());
+        map.insert("img-800.jpg".to_string(), "images/img-800.jpg".to_string());
+        let html = r#"<source srcset="img-400.jpg 400w, img-800.jpg 800w">"#;
+        let out = rewrite_html(html, "https://example.com", &map, false);
+        assert_eq!(
+            out,
+            r#"<source srcset="/images/img-400.jpg 400w, /images/img-800.jpg 800w">"#
+        );
+    }
+
+    #[test]
+    fn test_extract_existing_base_href() {
+        let html = r#"<head><base href="https://example.com/root/"></head>"#;
+        assert_eq!(
+            extract_base_href(html),
+            Some("https://example.com/root/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_base_href_missing() {
+        assert_eq!(extract_base_href("<head></head>"), None);
+    }
+
+    #[test]
+    fn test_inject_base_tag_when_absent() {
+        let html = "<head><title>t</title></head>";
+        let out = rewrite_html(html, "https://example.com/page", &HashMap::new(), true);
+        assert!(out.contains(r#"<base href="https://example.com/page/">"#));
+    }
+
+    #[test]
+    fn test_inject_base_tag_into_head_with_attributes() {
+        let html = r#"<head lang="en"><title>t</title></head>"#;
+        let out = rewrite_html(html, "https://example.com/page", &HashMap::new(), true);
+        assert_eq!(
+            out,
+            r#"<head lang="en"><base href="https://example.com/page/"><title>t</title></head>"#
+        );
+    }
+
+    #[test]
+    fn test_does_not_inject_when_base_present() {
+        let html = r#"<head><base href="https://other.example.com/"></head>"#;
+        let out = rewrite_html(html, "https://example.com/page", &HashMap::new(), true);
+        assert_eq!(out.matches("<base").count(), 1);
+        assert!(out.contains("other.example.com"));
+    }
+}
@@ -23,27 +23,64 @@
 //!     println!("Downloaded {total} assets");
 //!
 //!     // Serve offline
-//!     serve(8080).await?;
+//!     serve(8080, "page").await?;
 //!     Ok(())
 //! }
 //! ```
 
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     error::Error,
     net::SocketAddr,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use axum::Router;
+use axum::{extract::State, response::IntoResponse, Router};
 use thirtyfour::{By, DesiredCapabilities, WebDriver};
 use tokio::fs;
 use tower_http::services::ServeDir;
 
+mod archive;
+mod cache;
+mod css;
+mod css_assets;
+mod embed;
+mod html_rewrite;
+mod mime;
+mod url_util;
+mod wait;
+
+use cache::CacheStore;
+pub use wait::WaitStrategy;
+
 /// Shared set of already-downloaded URLs to avoid duplicates across pages.
 type DownloadedUrls = Arc<Mutex<HashSet<String>>>;
 
+/// Controls which cross-origin hosts get their assets mirrored locally.
+///
+/// By default no host is permitted, matching the original behavior where
+/// any asset off the base `website` is silently skipped. Opt specific CDN
+/// or font hosts in with `Scraper::with_allowed_hosts`, and exclude noisy
+/// ones (e.g. analytics) with `Scraper::with_denied_hosts`. Hosts are
+/// compared by exact name, with no wildcard or subdomain matching; a
+/// denied host is excluded even if it is also listed as allowed.
+#[derive(Debug, Clone, Default)]
+pub struct HostPolicy {
+    pub(crate) allowed: Option<HashSet<String>>,
+    pub(crate) denied: HashSet<String>,
+}
+
+impl HostPolicy {
+    pub(crate) fn permits(&self, host: &str) -> bool {
+        if self.denied.contains(host) {
+            return false;
+        }
+        self.allowed.as_ref().is_some_and(|allowed| allowed.contains(host))
+    }
+}
+
 /// A web scraper that captures JavaScript-rendered pages and their assets.
 ///
 /// Uses Selenium WebDriver to load pages in a real browser, wait for JavaScript
@@ -53,6 +90,10 @@ pub struct Scraper {
     client: reqwest::Client,
     downloaded: DownloadedUrls,
     website: String,
+    inject_base_tag: bool,
+    cache: Arc<Mutex<CacheStore>>,
+    host_policy: HostPolicy,
+    wait_strategy: WaitStrategy,
 }
 
 impl Scraper {
@@ -72,15 +113,56 @@ impl Scraper {
         let driver = WebDriver::new("http://127.0.0.1:4444", caps).await?;
         let client = reqwest::Client::new();
         let downloaded = Arc::new(Mutex::new(HashSet::new()));
+        let cache = Arc::new(Mutex::new(CacheStore::load().await));
 
         Ok(Self {
             driver,
             client,
             downloaded,
             website: website.to_string(),
+            inject_base_tag: false,
+            cache,
+            host_policy: HostPolicy::default(),
+            wait_strategy: WaitStrategy::default(),
         })
     }
 
+    /// Sets whether a `<base href>` tag should be injected into saved pages
+    /// that don't already have one.
+    ///
+    /// When set, pages without a `<base>` tag get one pointing at the
+    /// original page URL, so anchor links and any un-rewritten resources
+    /// still resolve. Pages that already have a `<base>` tag are left
+    /// untouched either way.
+    pub fn with_inject_base_tag(mut self, inject: bool) -> Self {
+        self.inject_base_tag = inject;
+        self
+    }
+
+    /// Allows cross-origin assets from the given hosts to be mirrored into
+    /// `page/_external/<host>/...` instead of being skipped.
+    pub fn with_allowed_hosts(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.host_policy
+            .allowed
+            .get_or_insert_with(HashSet::new)
+            .extend(hosts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Excludes the given hosts from cross-origin mirroring even if they
+    /// are also listed as allowed. Hosts are matched by exact name.
+    pub fn with_denied_hosts(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.host_policy.denied.extend(hosts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the policy used to decide when a page is "ready" to capture,
+    /// replacing the default fixed 2-second sleep.
+    pub fn with_wait_strategy(mut self, strategy: WaitStrategy) -> Self {
+        self.wait_strategy = strategy;
+        self
+    }
+
     /// Scrapes a page and all its assets.
     ///
     /// # Arguments
@@ -92,33 +174,134 @@ impl Scraper {
     /// The number of new assets downloaded for this page.
     pub async fn scrape_page(&self, path: &str) -> Result<usize, Box<dyn Error>> {
         println!("Scraping: {}/{path}", self.website);
-        self.driver.goto(format!("{}/{path}", self.website)).await?;
+        let page_url = format!("{}/{path}", self.website);
+        self.driver.goto(&page_url).await?;
+
+        // Wait until the page is ready, per the configured strategy
+        self.wait_strategy.wait(&self.driver).await?;
 
-        // Wait for dynamic content to load
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+        let source = self.driver.source().await?;
+        // Honor an existing `<base>` tag as the resolution root for
+        // *relative* references, exactly as a browser would. This is only
+        // ever used to turn a relative reference into an absolute URL —
+        // `self.website` remains the sole origin key for deciding whether
+        // an (already absolute) URL is same-origin, since a `<base>` with a
+        // non-root path (e.g. a locale prefix) must never affect how
+        // already-absolute site URLs are matched.
+        let base = html_rewrite::extract_base_href(&source).unwrap_or_else(|| page_url.clone());
 
         // Collect all resource URLs
         let urls = collect_resource_urls(&self.driver).await?;
         println!("  Found {} resources", urls.len());
 
-        // Download all assets
+        // Download all assets, tracking where each one landed locally so
+        // the saved HTML can be rewritten to point at it. Only record a
+        // mapping once the file is confirmed on disk — `download_asset`
+        // returns `false` both for "nothing new to do" (already cached or
+        // downloaded this run) and for genuine failures (network error,
+        // blocked host, write failure), so its return value alone can't
+        // tell those apart. Rewriting to a local path that doesn't exist
+        // would turn a working absolute URL into a guaranteed-broken one.
         let mut downloaded_count = 0;
+        let mut asset_map = HashMap::new();
+        for url in &urls {
+            if download_asset(&self.client, url, &base, &self.website, &self.downloaded, &self.cache, &self.host_policy).await {
+                downloaded_count += 1;
+            }
+            if let Some(local_path) = local_path_for(url, &self.website, &self.host_policy)
+                && fs::try_exists(format!("page/{local_path}")).await.unwrap_or(false)
+            {
+                asset_map.insert(url.clone(), local_path);
+            }
+        }
+        // Recurse into downloaded stylesheets for `@import`s, `@font-face`
+        // sources, and background images that never appear directly in the
+        // page's own markup. We gate on `host_policy` (a denied/non-allowed
+        // host is never even contacted, checked inside `discover_css_assets`
+        // itself for every nested reference it finds, not just the
+        // top-level stylesheet) and on `looks_like_stylesheet` — a cheap,
+        // no-network extension check — so non-CSS assets (images, scripts,
+        // fonts, video, ...) aren't handed to `discover_css_assets` just to
+        // have it fetch them once solely to sniff `Content-Type`.
+        // `discover_css_assets` still re-checks the real `Content-Type`
+        // before parsing, since extensionless endpoints like Google Fonts'
+        // `css?family=...` are real CSS too but wouldn't match by filename
+        // alone.
+        let mut seen_css_assets: HashSet<String> = HashSet::new();
+        let mut nested_urls = Vec::new();
         for url in &urls {
-            if download_asset(&self.client, url, &self.website, &self.downloaded).await {
+            if local_path_for(url, &self.website, &self.host_policy).is_none() {
+                continue;
+            }
+            if !css_assets::looks_like_stylesheet(url) {
+                continue;
+            }
+            let full_url = url_util::resolve_against(url, &base);
+            nested_urls.extend(
+                css_assets::discover_css_assets(&self.client, &full_url, &mut seen_css_assets, &self.website, &self.host_policy).await,
+            );
+        }
+        println!("  Found {} nested CSS assets", nested_urls.len());
+        for url in &nested_urls {
+            if download_asset(&self.client, url, &base, &self.website, &self.downloaded, &self.cache, &self.host_policy).await {
                 downloaded_count += 1;
             }
+            if let Some(local_path) = local_path_for(url, &self.website, &self.host_policy)
+                && fs::try_exists(format!("page/{local_path}")).await.unwrap_or(false)
+            {
+                asset_map.insert(url.clone(), local_path);
+            }
         }
+
         println!("  Downloaded {downloaded_count} new assets");
 
-        // Save the HTML
+        // Save the HTML, rewritten to reference the local assets
         fs::create_dir_all(format!("page/{path}")).await?;
-        let source = self.driver.source().await?;
-        fs::write(format!("page/{path}/index.html"), source.as_bytes()).await?;
+        let html = html_rewrite::rewrite_html(&source, &page_url, &asset_map, self.inject_base_tag);
+        fs::write(format!("page/{path}/index.html"), html.as_bytes()).await?;
         println!("  Saved HTML");
 
         Ok(downloaded_count)
     }
 
+    /// Scrapes a page into a single self-contained `.html` file.
+    ///
+    /// Every asset the page references — images, scripts, stylesheets,
+    /// inline `style` backgrounds, `<source srcset>` — is inlined as a
+    /// `data:` URI, so the result opens directly in a browser without the
+    /// `serve` binary or a sibling asset directory. Stylesheets reached via
+    /// `<link>` are fetched, have their own `url(...)`/`@import` targets
+    /// recursively embedded, and are inlined as a `<style>` tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The relative path to scrape (e.g., "en_us/ids")
+    ///
+    /// # Returns
+    ///
+    /// The path of the saved `.html` file.
+    pub async fn scrape_page_embedded(&self, path: &str) -> Result<PathBuf, Box<dyn Error>> {
+        println!("Scraping (embedded): {}/{path}", self.website);
+        self.driver.goto(format!("{}/{path}", self.website)).await?;
+
+        // Wait until the page is ready, per the configured strategy
+        self.wait_strategy.wait(&self.driver).await?;
+
+        let urls = collect_resource_urls(&self.driver).await?;
+        println!("  Found {} resources", urls.len());
+
+        let html = embed::embed_page(&self.driver, &self.client, &self.website, &urls).await?;
+
+        let file_path = PathBuf::from(format!("page/{path}.html"));
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&file_path, html.as_bytes()).await?;
+        println!("  Saved self-contained {}", file_path.display());
+
+        Ok(file_path)
+    }
+
     /// Returns the total number of unique assets downloaded.
     pub fn total_assets(&self) -> usize {
         self.downloaded
@@ -130,20 +313,52 @@ impl Scraper {
     /// Closes the browser and returns the total number of assets downloaded.
     pub async fn finish(self) -> Result<usize, Box<dyn Error>> {
         let total = self.total_assets();
+        let entries = self.cache.lock().unwrap_or_else(|e| e.into_inner()).snapshot();
+        cache::save_entries(&entries).await?;
         self.driver.quit().await?;
         Ok(total)
     }
+
+    /// Closes the browser and packs the scraped `page/` directory into a
+    /// single zip archive at `path`, alongside a `manifest.json` mapping
+    /// each archived entry back to its original URL, content type, and
+    /// capture time.
+    ///
+    /// # Returns
+    ///
+    /// The number of files packed into the archive.
+    pub async fn finish_to_archive(self, path: impl AsRef<Path>) -> Result<usize, Box<dyn Error>> {
+        let entries = self.cache.lock().unwrap_or_else(|e| e.into_inner()).snapshot();
+        cache::save_entries(&entries).await?;
+        self.driver.quit().await?;
+
+        let captured_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let count = archive::pack(Path::new("page"), path.as_ref(), &self.website, captured_at)
+            .await
+            .map_err(|e| -> Box<dyn Error> { e })?;
+        Ok(count)
+    }
 }
 
 /// Starts a static file server to serve the downloaded pages.
 ///
-/// Serves files from the `page/` directory.
+/// `source` is either a directory (served with `ServeDir` as before) or a
+/// path to a `.zip` archive produced by `Scraper::finish_to_archive`, in
+/// which case the whole archive is loaded into memory at startup and each
+/// request is looked up by its archived path and served with the content
+/// type recorded in the archive's manifest.
 ///
 /// # Arguments
 ///
 /// * `port` - The port to listen on
-pub async fn serve(port: u16) -> Result<(), Box<dyn Error>> {
-    let app = Router::new().fallback_service(ServeDir::new("page"));
+/// * `source` - The `page/` directory or `.zip` archive to serve
+pub async fn serve(port: u16, source: &str) -> Result<(), Box<dyn Error>> {
+    let app = if source.ends_with(".zip") {
+        let store = Arc::new(archive::load(source).await.map_err(|e| -> Box<dyn Error> { e })?);
+        Router::new().fallback(serve_from_archive).with_state(store)
+    } else {
+        Router::new().fallback_service(ServeDir::new(source))
+    };
 
     let addr = SocketAddr::from(([127, 0, 0, 1], port));
     println!("Serving offline pages at http://{addr}");
@@ -156,6 +371,30 @@ pub async fn serve(port: u16) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Resolves an incoming request against an in-memory archive manifest and
+/// streams the matching entry out, falling back to `<path>/index.html`.
+async fn serve_from_archive(
+    State(store): State<Arc<archive::ArchiveStore>>,
+    uri: axum::http::Uri,
+) -> axum::response::Response {
+    let path = uri.path().trim_start_matches('/').trim_end_matches('/');
+    let index_candidate = if path.is_empty() {
+        "index.html".to_string()
+    } else {
+        format!("{path}/index.html")
+    };
+    let candidates = [path.to_string(), index_candidate];
+
+    for candidate in candidates {
+        if let Some((content_type, bytes)) = store.get(&candidate) {
+            return ([(axum::http::header::CONTENT_TYPE, content_type.clone())], bytes.clone())
+                .into_response();
+        }
+    }
+
+    (axum::http::StatusCode::NOT_FOUND, "Not found").into_response()
+}
+
 /// Normalizes a URL path for local storage.
 ///
 /// Handles:
@@ -185,18 +424,44 @@ pub fn normalize_url_path(url: &str, website: &str) -> Option<String> {
     path.filter(|p| !p.is_empty())
 }
 
+/// Resolves an asset URL to its local save path: same-origin URLs go
+/// through `normalize_url_path`, cross-origin ones are mirrored under
+/// `_external/<host>/...` if `policy` permits their host.
+pub(crate) fn local_path_for(url: &str, website: &str, policy: &HostPolicy) -> Option<String> {
+    if url.starts_with("//") && url_util::extract_host(url) != url_util::extract_host(website) {
+        return url_util::external_local_path(url, policy);
+    }
+    normalize_url_path(url, website).or_else(|| url_util::external_local_path(url, policy))
+}
+
 /// Downloads an asset and saves it locally.
+///
+/// `base` is the resolution root for a relative `url` (the page's own URL,
+/// or an extracted `<base href>`); `website` is the site origin used to
+/// decide, from `url` as given, whether it is same-origin. The two are kept
+/// separate because a `<base>` with a non-root path must not change how
+/// already-absolute site URLs are matched.
+///
+/// Consults `cache` first: a still-fresh entry skips the network entirely,
+/// and a stale one is revalidated with `If-None-Match`/`If-Modified-Since`,
+/// treating a `304 Not Modified` as "keep the existing file, not a new
+/// download" rather than refetching its bytes — unless the file the cache
+/// entry is supposed to validate is missing on disk, in which case the
+/// entry is evicted and a fresh unconditional GET is issued instead.
 async fn download_asset(
     client: &reqwest::Client,
     url: &str,
+    base: &str,
     website: &str,
     downloaded: &DownloadedUrls,
+    cache: &Arc<Mutex<CacheStore>>,
+    host_policy: &HostPolicy,
 ) -> bool {
-    let Some(local_path) = normalize_url_path(url, website) else {
+    let Some(local_path) = local_path_for(url, website, host_policy) else {
         return false;
     };
 
-    // Check if already downloaded
+    // Check if already downloaded this run
     {
         let urls = downloaded.lock().unwrap_or_else(|e| e.into_inner());
         if urls.contains(&local_path) {
@@ -204,28 +469,69 @@ async fn download_asset(
         }
     }
 
-    // Build full URL for download
-    let full_url = if url.starts_with("http") {
-        url.to_string()
-    } else if let Some(stripped) = url.strip_prefix("//") {
-        format!("https://{stripped}")
+    let file_path = PathBuf::from(format!("page/{local_path}"));
+    let cached_entry = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&local_path).cloned();
+
+    if let Some(entry) = &cached_entry
+        && entry.is_fresh()
+        && fs::try_exists(&file_path).await.unwrap_or(false)
+    {
+        downloaded.lock().unwrap_or_else(|e| e.into_inner()).insert(local_path);
+        return false;
+    }
+
+    // Build full URL for download, resolving a relative `url` against `base`
+    // (the page URL or its `<base href>`), not the site origin.
+    let full_url = url_util::resolve_against(url, base);
+
+    let mut request = client.get(&full_url);
+    if let Some(entry) = &cached_entry {
+        if let Some(etag) = &entry.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let Ok(response) = request.send().await else {
+        return false;
+    };
+
+    let response = if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if fs::try_exists(&file_path).await.unwrap_or(false) {
+            let entry = cache::entry_from_headers(response.headers());
+            cache.lock().unwrap_or_else(|e| e.into_inner()).insert(local_path.clone(), entry);
+            downloaded.lock().unwrap_or_else(|e| e.into_inner()).insert(local_path);
+            return false;
+        }
+
+        // The cache says this asset is unchanged, but the file it's
+        // supposed to validate isn't on disk (deleted out from under us,
+        // partial cleanup, ...). The server will keep saying 304 for this
+        // `ETag` forever, so drop the stale entry and re-issue a plain,
+        // unconditional GET rather than giving up on ever re-acquiring it.
+        cache.lock().unwrap_or_else(|e| e.into_inner()).remove(&local_path);
+        let Ok(response) = client.get(&full_url).send().await else {
+            return false;
+        };
+        response
     } else {
-        format!("{website}/{}", url.trim_start_matches('/'))
+        response
+    };
+
+    let entry = cache::entry_from_headers(response.headers());
+    let Ok(bytes) = response.bytes().await else {
+        return false;
     };
 
-    // Download and save
-    if let Ok(response) = client.get(&full_url).send().await
-        && let Ok(bytes) = response.bytes().await
+    if let Some(parent) = file_path.parent()
+        && fs::create_dir_all(parent).await.is_ok()
+        && fs::write(&file_path, &bytes).await.is_ok()
     {
-        let file_path = PathBuf::from(format!("page/{local_path}"));
-        if let Some(parent) = file_path.parent()
-            && fs::create_dir_all(parent).await.is_ok()
-            && fs::write(&file_path, &bytes).await.is_ok()
-        {
-            let mut urls = downloaded.lock().unwrap_or_else(|e| e.into_inner());
-            urls.insert(local_path);
-            return true;
-        }
+        cache.lock().unwrap_or_else(|e| e.into_inner()).insert(local_path.clone(), entry);
+        downloaded.lock().unwrap_or_else(|e| e.into_inner()).insert(local_path);
+        return true;
     }
 
     false
@@ -0,0 +1,44 @@
+//! MIME type sniffing shared by the self-contained HTML embedder and the
+//! archive packer/server.
+
+/// Guesses a MIME type from an HTTP `Content-Type` header, falling back to
+/// the URL's (or local path's) file extension when the header is missing
+/// or generic.
+pub(crate) fn sniff_mime(url: &str, content_type: Option<&str>) -> String {
+    if let Some(ct) = content_type {
+        let ct = ct.split(';').next().unwrap_or(ct).trim();
+        if !ct.is_empty() && ct != "application/octet-stream" {
+            return ct.to_string();
+        }
+    }
+
+    let ext = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .rsplit('.')
+        .next()
+        .unwrap_or("");
+
+    match ext.to_ascii_lowercase().as_str() {
+        "css" => "text/css",
+        "js" | "mjs" => "application/javascript",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
@@ -0,0 +1,117 @@
+//! URL resolution helpers shared by the scraper and the CSS/HTML embedding
+//! passes.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Resolves a possibly-relative URL reference against a base URL.
+///
+/// Handles absolute `http(s)://` URLs, protocol-relative `//host/path`
+/// URLs, root-relative `/path` URLs, and relative paths resolved against
+/// the base's own directory (not the site root) — the same rule browsers
+/// use for `url(...)` references inside a stylesheet.
+pub(crate) fn resolve_against(reference: &str, base: &str) -> String {
+    if reference.starts_with("http://") || reference.starts_with("https://") {
+        return reference.to_string();
+    }
+
+    if let Some(stripped) = reference.strip_prefix("//") {
+        let scheme = base.split("//").next().unwrap_or("https:");
+        return format!("{scheme}//{stripped}");
+    }
+
+    let scheme_end = base.find("//").map(|i| i + 2).unwrap_or(0);
+    let host_end = base[scheme_end..]
+        .find('/')
+        .map(|i| scheme_end + i)
+        .unwrap_or(base.len());
+
+    if let Some(rest) = reference.strip_prefix('/') {
+        return format!("{}/{rest}", &base[..host_end]);
+    }
+
+    let dir_end = base.rfind('/').filter(|&i| i >= host_end).unwrap_or(host_end);
+    format!("{}/{reference}", &base[..dir_end])
+}
+
+/// Extracts the host portion of an absolute or protocol-relative URL.
+pub(crate) fn extract_host(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("//"))?;
+    let host = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// Maps a cross-origin URL into a deterministic local mirror path under
+/// `_external/<host>/<path>`, or `None` if the host isn't permitted by
+/// `policy` or the URL has no host at all.
+///
+/// A non-empty query string is folded into the filename as a short hash
+/// suffix, since extensionless endpoints like Google Fonts'
+/// `css?family=Roboto` vs. `css?family=Open+Sans` would otherwise collide
+/// on the same local path and silently overwrite one another.
+pub(crate) fn external_local_path(url: &str, policy: &crate::HostPolicy) -> Option<String> {
+    let url = url.split('#').next().unwrap_or(url);
+    let host = extract_host(url)?;
+    if !policy.permits(&host) {
+        return None;
+    }
+
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("//"))?;
+    let after_host = rest.split_once('/').map_or("", |(_, rest)| rest).trim_start_matches('/');
+    let (path, query) = after_host.split_once('?').unwrap_or((after_host, ""));
+    if path.is_empty() {
+        return None;
+    }
+
+    if query.is_empty() {
+        return Some(format!("_external/{host}/{path}"));
+    }
+
+    let mut hasher = DefaultHasher::new();
+    query.hash(&mut hasher);
+    Some(format!("_external/{host}/{path}-{:x}", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_host_absolute() {
+        assert_eq!(
+            extract_host("https://fonts.googleapis.com/css?family=Roboto"),
+            Some("fonts.googleapis.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_host_protocol_relative() {
+        assert_eq!(
+            extract_host("//cdn.example.com/lib.js"),
+            Some("cdn.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_host_relative_path() {
+        assert_eq!(extract_host("images/logo.png"), None);
+    }
+
+    #[test]
+    fn test_external_local_path_distinguishes_by_query() {
+        let policy = crate::HostPolicy {
+            allowed: Some(["fonts.googleapis.com".to_string()].into_iter().collect()),
+            denied: Default::default(),
+        };
+        let roboto = external_local_path("https://fonts.googleapis.com/css?family=Roboto", &policy).unwrap();
+        let open_sans =
+            external_local_path("https://fonts.googleapis.com/css?family=Open+Sans", &policy).unwrap();
+        assert_ne!(roboto, open_sans);
+    }
+}
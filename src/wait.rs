@@ -0,0 +1,119 @@
+//! Pluggable page-readiness policies used before capturing a scraped page.
+
+use std::{
+    error::Error,
+    time::{Duration, Instant},
+};
+
+use thirtyfour::{By, WebDriver};
+
+/// Default ceiling for [`WaitStrategy::Selector`], which has no timeout
+/// field of its own.
+const SELECTOR_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for a page to be "ready" before capturing it.
+///
+/// Defaults to `Fixed(Duration::from_secs(2))`, matching the scraper's
+/// original behavior.
+#[derive(Debug, Clone)]
+pub enum WaitStrategy {
+    /// Wait a fixed duration, with no readiness check at all.
+    Fixed(Duration),
+    /// Wait for `document.readyState === 'complete'`, then poll
+    /// `performance.getEntriesByType('resource')` until the resource count
+    /// holds steady for `quiet` consecutive polls — a network-idle
+    /// heuristic — bailing out after `timeout` regardless.
+    NetworkIdle { quiet: u32, timeout: Duration },
+    /// Wait for a CSS selector to appear in the DOM.
+    Selector(By),
+}
+
+impl Default for WaitStrategy {
+    fn default() -> Self {
+        Self::Fixed(Duration::from_secs(2))
+    }
+}
+
+impl WaitStrategy {
+    /// Blocks until the page satisfies this strategy or its timeout elapses;
+    /// an elapsed timeout is not reported as an error.
+    pub(crate) async fn wait(&self, driver: &WebDriver) -> Result<(), Box<dyn Error>> {
+        match self {
+            Self::Fixed(duration) => {
+                tokio::time::sleep(*duration).await;
+                Ok(())
+            }
+            Self::NetworkIdle { quiet, timeout } => wait_network_idle(driver, *quiet, *timeout).await,
+            Self::Selector(by) => wait_for_selector(driver, by.clone()).await,
+        }
+    }
+}
+
+async fn document_ready_state(driver: &WebDriver) -> String {
+    driver
+        .execute("return document.readyState", vec![])
+        .await
+        .ok()
+        .and_then(|r| serde_json::from_value(r.json().clone()).ok())
+        .unwrap_or_default()
+}
+
+async fn resource_count(driver: &WebDriver) -> usize {
+    driver
+        .execute("return performance.getEntriesByType('resource').length", vec![])
+        .await
+        .ok()
+        .and_then(|r| serde_json::from_value(r.json().clone()).ok())
+        .unwrap_or(0)
+}
+
+async fn wait_network_idle(driver: &WebDriver, quiet: u32, timeout: Duration) -> Result<(), Box<dyn Error>> {
+    let deadline = Instant::now() + timeout;
+    const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+    while document_ready_state(driver).await != "complete" && Instant::now() < deadline {
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    let mut stable_rounds = 0;
+    let mut last_count = None;
+    while Instant::now() < deadline {
+        let count = resource_count(driver).await;
+        if last_count == Some(count) {
+            stable_rounds += 1;
+            if stable_rounds >= quiet {
+                break;
+            }
+        } else {
+            stable_rounds = 0;
+            last_count = Some(count);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    Ok(())
+}
+
+async fn wait_for_selector(driver: &WebDriver, by: By) -> Result<(), Box<dyn Error>> {
+    let deadline = Instant::now() + SELECTOR_TIMEOUT;
+    while Instant::now() < deadline {
+        if !driver.find_all(by.clone()).await.unwrap_or_default().is_empty() {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_fixed_two_seconds() {
+        match WaitStrategy::default() {
+            WaitStrategy::Fixed(duration) => assert_eq!(duration, Duration::from_secs(2)),
+            other => panic!("expected WaitStrategy::Fixed(2s), got {other:?}"),
+        }
+    }
+}